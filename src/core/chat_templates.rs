@@ -0,0 +1,39 @@
+//! Built-in chat-template fallbacks, one per GGUF `general.architecture` we
+//! support. Used by `ModelInstance::apply_chat_template` only when the model
+//! doesn't ship its own `tokenizer.chat_template` metadata or a sibling
+//! `chat_template.txt`. These mirror the templates each model family
+//! publishes in its Hugging Face `tokenizer_config.json`.
+
+const LLAMA2_TEMPLATE: &str = "{% if messages[0]['role'] == 'system' %}{% set loop_messages = messages[1:] %}{% set system_message = messages[0]['content'] %}{% else %}{% set loop_messages = messages %}{% set system_message = system %}{% endif %}{% for message in loop_messages %}{% if loop.index0 == 0 and system_message %}{% set content = '<<SYS>>\n' + system_message + '\n<</SYS>>\n\n' + message['content'] %}{% else %}{% set content = message['content'] %}{% endif %}{% if message['role'] == 'user' %}{{ bos_token + '[INST] ' + content | trim + ' [/INST]' }}{% elif message['role'] == 'assistant' %}{{ ' ' + content | trim + ' ' + eos_token }}{% elif message['role'] == 'tool' %}{{ ' [TOOL_RESULT] ' + content | trim + ' [/TOOL_RESULT]' }}{% endif %}{% endfor %}";
+
+const MISTRAL_TEMPLATE: &str = "{% if messages[0]['role'] == 'system' %}{% set loop_messages = messages[1:] %}{% set system_message = messages[0]['content'] %}{% else %}{% set loop_messages = messages %}{% set system_message = '' %}{% endif %}{{ bos_token }}{% for message in loop_messages %}{% if message['role'] == 'user' %}{{ '[INST] ' + (system_message + '\n\n' if loop.index0 == 0 and system_message else '') + message['content'] + ' [/INST]' }}{% elif message['role'] == 'assistant' %}{{ message['content'] + eos_token }}{% elif message['role'] == 'tool' %}{{ '[TOOL_RESULTS] ' + message['content'] + ' [/TOOL_RESULTS]' }}{% endif %}{% endfor %}";
+
+const GEMMA_TEMPLATE: &str = "{{ bos_token }}{% for message in messages %}{{ '<start_of_turn>' + (message['role'] if message['role'] != 'assistant' else 'model') + '\n' + message['content'] | trim + '<end_of_turn>\n' }}{% endfor %}{% if add_generation_prompt %}{{ '<start_of_turn>model\n' }}{% endif %}";
+
+const PHI_TEMPLATE: &str = "{% for message in messages %}{{ '<|' + message['role'] + '|>\n' + message['content'] + '<|end|>\n' }}{% endfor %}{% if add_generation_prompt %}{{ '<|assistant|>\n' }}{% endif %}";
+
+/// Returns the built-in template for a detected GGUF architecture string.
+/// Unknown architectures fall back to the Llama-2 template (whereas
+/// `InferenceEngine::load_model` rejects an unknown architecture outright).
+pub fn default_for(architecture: &str) -> &'static str {
+    match architecture {
+        "mistral" => MISTRAL_TEMPLATE,
+        "gemma" => GEMMA_TEMPLATE,
+        "phi" | "phi2" | "phi3" => PHI_TEMPLATE,
+        _ => LLAMA2_TEMPLATE,
+    }
+}
+
+/// Returns the `(bos_token, eos_token)` string pair a built-in template
+/// should render for a detected GGUF architecture. Architectures don't agree
+/// on these: Gemma's are `<bos>`/`<eos>`, not Llama-2's `<s>`/`</s>`, and
+/// Phi models typically have no true BOS at all. `apply_chat_template`
+/// renders each of these only if the model's tokenizer has it in its
+/// vocabulary, and an empty string otherwise.
+pub fn special_tokens_for(architecture: &str) -> (&'static str, &'static str) {
+    match architecture {
+        "gemma" => ("<bos>", "<eos>"),
+        "phi" | "phi2" | "phi3" => ("", "<|endoftext|>"),
+        _ => ("<s>", "</s>"),
+    }
+}
@@ -0,0 +1,77 @@
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config as BertConfig};
+use tokenizers::Tokenizer;
+use std::path::Path;
+
+/// Loads a BERT/sentence-transformer style embedding model and turns text
+/// into fixed-length vectors by mean-pooling the last hidden state over the
+/// attention mask and L2-normalizing the result. This is the foundation for
+/// semantic search / RAG on top of the server; it's loaded into the same
+/// `models` map as generation models, just under a different `LoadedModel`
+/// variant.
+pub struct SentenceEmbedder {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+impl SentenceEmbedder {
+    /// Loads a HF-layout embedding model directory: `config.json`,
+    /// `tokenizer.json`, and `model.safetensors`.
+    pub fn load(model_dir: &Path, device: &Device) -> anyhow::Result<Self> {
+        let config_path = model_dir.join("config.json");
+        let weights_path = model_dir.join("model.safetensors");
+        let tokenizer_path = model_dir.join("tokenizer.json");
+
+        let config: BertConfig = serde_json::from_str(&std::fs::read_to_string(&config_path).map_err(|e| {
+            anyhow::anyhow!("Failed to read embedding model config {:?}: {}", config_path, e)
+        })?)?;
+
+        let tokenizer = Tokenizer::from_file(&tokenizer_path)
+            .map_err(|e| anyhow::anyhow!("Failed to load embedding tokenizer from {:?}: {}", tokenizer_path, e))?;
+
+        let vb = unsafe {
+            VarBuilder::from_mmaped_safetensors(&[weights_path.clone()], DType::F32, device)
+                .map_err(|e| anyhow::anyhow!("Failed to mmap embedding weights {:?}: {}", weights_path, e))?
+        };
+        let model = BertModel::load(vb, &config)?;
+
+        Ok(Self {
+            model,
+            tokenizer,
+            device: device.clone(),
+        })
+    }
+
+    /// Embeds a batch of strings, one fixed-length vector per input.
+    pub fn embed(&self, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        inputs.iter().map(|text| self.embed_one(text)).collect()
+    }
+
+    fn embed_one(&self, text: &str) -> anyhow::Result<Vec<f32>> {
+        let encoding = self
+            .tokenizer
+            .encode(text, true)
+            .map_err(|e| anyhow::anyhow!("Failed to encode embedding input: {}", e))?;
+
+        let token_ids = Tensor::new(encoding.get_ids(), &self.device)?.unsqueeze(0)?;
+        let attention_mask = Tensor::new(encoding.get_attention_mask(), &self.device)?.unsqueeze(0)?;
+        let token_type_ids = token_ids.zeros_like()?;
+
+        let hidden_state = self.model.forward(&token_ids, &token_type_ids, Some(&attention_mask))?;
+
+        // Mean-pool the last hidden state over the attention mask, then
+        // L2-normalize so cosine similarity reduces to a dot product.
+        let mask = attention_mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+        let masked_hidden = hidden_state.broadcast_mul(&mask)?;
+        let summed = masked_hidden.sum(1)?;
+        let counts = mask.sum(1)?;
+        let mean_pooled = summed.broadcast_div(&counts)?.squeeze(0)?;
+
+        let norm = mean_pooled.sqr()?.sum_all()?.sqrt()?;
+        let normalized = mean_pooled.broadcast_div(&norm)?;
+
+        Ok(normalized.to_vec1::<f32>()?)
+    }
+}
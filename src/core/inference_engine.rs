@@ -1,18 +1,70 @@
+use candle_core::quantized::gguf_file;
 use candle_core::{Device, Tensor, DType};
 use candle_nn::VarBuilder;
-use candle_transformers::models::quantized_llama::ModelWeights;
+use candle_transformers::models::quantized_llama::ModelWeights as LlamaWeights;
 use candle_transformers::models::quantized_mistral::ModelWeights as MistralWeights;
 use candle_transformers::models::gemma::ModelWeights as GemmaWeights;
 use candle_transformers::models::phi::ModelWeights as PhiWeights;
-use candle_transformers::generation::LogitsProcessor;
+use candle_transformers::generation::{LogitsProcessor, Sampling};
 use candle_transformers::utils::model as transformers_model;
+use async_stream::stream;
+use futures::Stream;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs::File;
 use std::path::Path;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex};
 use tracing::{info, warn, error};
 use uuid::Uuid;
 
+/// The set of GGUF architectures we know how to load.
+///
+/// `ModelInstance` used to hold a single concrete `ModelWeights` type, which
+/// only ever worked for Llama-family checkpoints. Keeping one variant per
+/// `candle_transformers` quantized model lets `load_model` dispatch on the
+/// GGUF `general.architecture` metadata key instead of assuming Llama.
+pub enum LoadedWeights {
+    Llama(LlamaWeights),
+    Mistral(MistralWeights),
+    Gemma(GemmaWeights),
+    Phi(PhiWeights),
+}
+
+/// Why `ModelInstance::generate_with_callback` stopped producing tokens.
+/// Surfaced as the OpenAI-style `finish_reason` (`"stop"`/`"length"`); the
+/// Ollama-shaped `GenerationResponse`/`ChatResponse` do not report it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// The model produced its end-of-sequence token.
+    Stop,
+    /// `max_tokens` was reached before the model stopped on its own.
+    Length,
+}
+
+/// Real (not estimated) token accounting for a single generation, used to
+/// populate both the Ollama `*_eval_count` fields and the OpenAI `usage`
+/// block from the same numbers.
+#[derive(Debug, Clone, Copy)]
+pub struct GenerationStats {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub finish_reason: FinishReason,
+}
+
+impl LoadedWeights {
+    fn forward(&mut self, tokens: &[u32], index: usize, device: &Device) -> anyhow::Result<Tensor> {
+        let logits = match self {
+            LoadedWeights::Llama(w) => w.forward(tokens, index, device)?,
+            LoadedWeights::Mistral(w) => w.forward(tokens, index, device)?,
+            LoadedWeights::Gemma(w) => w.forward(tokens, index, device)?,
+            LoadedWeights::Phi(w) => w.forward(tokens, index, device)?,
+        };
+        Ok(logits)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InferenceConfig {
     pub temperature: f32,
@@ -36,6 +88,23 @@ impl Default for InferenceConfig {
     }
 }
 
+impl InferenceConfig {
+    /// Builds the sampling strategy this config implies, used by the decode
+    /// loop in `ModelInstance::generate_with_callback`.
+    fn sampling(&self) -> Sampling {
+        if self.temperature <= 0.0 {
+            return Sampling::ArgMax;
+        }
+        let temperature = self.temperature as f64;
+        match (self.top_k, self.top_p) {
+            (k, p) if k > 0 && p > 0.0 && p < 1.0 => Sampling::TopKThenTopP { k, p: p as f64, temperature },
+            (k, _) if k > 0 => Sampling::TopK { k, temperature },
+            (_, p) if p > 0.0 && p < 1.0 => Sampling::TopP { p: p as f64, temperature },
+            _ => Sampling::All { temperature },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerationRequest {
     pub model: String,
@@ -48,8 +117,62 @@ pub struct GenerationRequest {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
-    pub role: String, // "system", "user", "assistant"
+    pub role: String, // "system", "user", "assistant", "tool"
+    #[serde(default)]
     pub content: String,
+    /// Populated on an assistant message when the model asked to call one
+    /// or more tools instead of answering directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// Set on a `"tool"` role message carrying the result of a prior
+    /// `tool_calls` entry back to the model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+/// A JSON-schema function declaration a chat request can offer the model,
+/// mirroring the OpenAI tool-calling shape.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolDeclaration {
+    Function { function: ToolFunctionDef },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolFunctionDef {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub parameters: serde_json::Value,
+}
+
+impl ToolFunctionDef {
+    /// Callers may auto-execute a `may_`-prefixed function without asking
+    /// for confirmation first; anything else is assumed side-effecting.
+    pub fn is_read_only(&self) -> bool {
+        self.name.starts_with("may_")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallFunction {
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub function: ToolCallFunction,
+}
+
+impl ToolCall {
+    pub fn requires_confirmation(&self) -> bool {
+        !self.function.name.starts_with("may_")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +180,11 @@ pub struct ChatRequest {
     pub model: String,
     pub messages: Vec<ChatMessage>,
     pub stream: bool,
+    /// Function declarations the model may call instead of answering
+    /// directly. When present, `InferenceEngine::chat` injects them into the
+    /// rendered prompt and parses the model's output for a tool-call block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ToolDeclaration>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,6 +202,21 @@ pub struct GenerationResponse {
     pub eval_duration: Option<u64>,
 }
 
+/// Submits several prompts against the same model in one call. Honors
+/// `InferenceEngine::max_client_batch_size`: requests with more prompts than
+/// that are rejected rather than silently truncated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchGenerationRequest {
+    pub model: String,
+    pub prompts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchGenerationResponse {
+    pub model: String,
+    pub responses: Vec<GenerationResponse>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatResponse {
     pub model: String,
@@ -88,65 +231,235 @@ pub struct ChatResponse {
     pub eval_duration: Option<u64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingRequest {
+    pub model: String,
+    pub input: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingResponse {
+    pub model: String,
+    pub embeddings: Vec<Vec<f32>>,
+}
+
+/// `usage` block shared by `/v1/completions` and `/v1/chat/completions`,
+/// populated from the real tokenizer counts in `GenerationStats` rather
+/// than character-length guesses.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct OpenAiUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+impl OpenAiUsage {
+    fn from_stats(stats: GenerationStats) -> Self {
+        Self {
+            prompt_tokens: stats.prompt_tokens,
+            completion_tokens: stats.completion_tokens,
+            total_tokens: stats.prompt_tokens + stats.completion_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionRequest {
+    pub model: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    #[serde(default)]
+    pub n: Option<u32>,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionChoice {
+    pub index: u32,
+    pub text: String,
+    pub logprobs: Option<serde_json::Value>,
+    pub finish_reason: FinishReason,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionResponse {
+    pub id: String,
+    pub object: &'static str,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<CompletionChoice>,
+    pub usage: OpenAiUsage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<usize>,
+    #[serde(default)]
+    pub n: Option<u32>,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionChoice {
+    pub index: u32,
+    pub message: ChatMessage,
+    pub logprobs: Option<serde_json::Value>,
+    pub finish_reason: FinishReason,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionResponse {
+    pub id: String,
+    pub object: &'static str,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<ChatCompletionChoice>,
+    pub usage: OpenAiUsage,
+}
+
 pub struct ModelInstance {
     pub model_id: String,
-    pub weights: ModelWeights,
+    pub weights: LoadedWeights,
     pub tokenizer: tokenizers::Tokenizer,
     pub device: Device,
     pub config: InferenceConfig,
     pub session_id: String,
+    /// Wall-clock time taken by `InferenceEngine::load_model` to read the GGUF
+    /// file and construct `weights`/`tokenizer`, in nanoseconds. Reported back
+    /// to clients via `GenerationResponse::load_duration`.
+    pub load_duration: u64,
+    /// GGUF `general.architecture` value, e.g. `"llama"`, `"mistral"`,
+    /// `"gemma"`, `"phi"`. Used to pick the right built-in chat template
+    /// fallback in `apply_chat_template`.
+    pub architecture: String,
+    /// Chat template source, read from the GGUF `tokenizer.chat_template`
+    /// metadata key or a sibling `chat_template.txt`. `None` means fall back
+    /// to the built-in default for `architecture`.
+    pub chat_template: Option<String>,
+    /// End-of-sequence token id, read from the GGUF `tokenizer.ggml.eos_token_id`
+    /// metadata key. Architectures disagree on this (e.g. Gemma's EOS id isn't
+    /// Llama's), so it has to come from the model's own metadata rather than a
+    /// single hardcoded id.
+    pub eos_token_id: u32,
 }
 
 impl ModelInstance {
-    fn apply_chat_template(&self, messages: &[ChatMessage], system: Option<&str>) -> String {
-        // Simple chat template - could be enhanced with proper jinja2 templates
-        let mut conversation = String::new();
-        
-        if let Some(system_msg) = system {
-            conversation.push_str(&format!("<s>[INST] <<SYS>>{}<</SYS>>", system_msg));
-        }
-
-        for (i, message) in messages.iter().enumerate() {
-            if message.role == "user" {
-                if i == 0 && system.is_none() {
-                    conversation.push_str(&format!("<s>[INST] {}", message.content));
-                } else {
-                    conversation.push_str(&format!(" {} </s><s>[INST] {}", message.content));
-                }
-            } else if message.role == "assistant" {
-                conversation.push_str(&format!(" {} </s>", message.content));
-            }
-        }
+    /// Renders `messages` through this model's chat template: the one it
+    /// shipped with, or the built-in default for its architecture. `system`,
+    /// when given, is rendered as the only system-role message; any
+    /// system-role entries in `messages` are left out either way. Supports the `messages`, `role`,
+    /// `content`, and `add_generation_prompt` variables standard HF
+    /// templates use.
+    fn apply_chat_template(&self, messages: &[ChatMessage], system: Option<&str>) -> anyhow::Result<String> {
+        let template_str = self
+            .chat_template
+            .clone()
+            .unwrap_or_else(|| crate::core::chat_templates::default_for(&self.architecture).to_string());
 
-        if !conversation.contains("[/INST]") {
-            conversation.push_str(" [/INST]");
+        let mut env = minijinja::Environment::new();
+        env.add_template("chat", &template_str)
+            .map_err(|e| anyhow::anyhow!("Invalid chat template for model {}: {}", self.model_id, e))?;
+        let template = env.get_template("chat").unwrap();
+
+        // Which strings count as BOS/EOS depends on the model family (Gemma's
+        // are `<bos>`/`<eos>`, not Llama-2's `<s>`/`</s>`), so look them up by
+        // `architecture` rather than assuming Llama-2's spelling for every model.
+        let (bos_candidate, eos_candidate) = crate::core::chat_templates::special_tokens_for(&self.architecture);
+        let bos_token = self.tokenizer.token_to_id(bos_candidate).map(|_| bos_candidate).unwrap_or_default();
+        let eos_token = self.tokenizer.token_to_id(eos_candidate).map(|_| eos_candidate).unwrap_or_default();
+
+        // Templates disagree on whether they read the separate `system`
+        // variable or expect system content folded into `messages[0]`
+        // themselves (most do the latter, and some ignore `system` entirely).
+        // Fold `system` into a single system-role entry at the front of what
+        // gets rendered, in place of the caller's own system message if it
+        // sent one, so every template sees it the same way it sees any other
+        // message.
+        let mut rendered_messages: Vec<&ChatMessage> = messages.iter().filter(|m| m.role != "system").collect();
+        let system_message = system.map(|content| ChatMessage {
+            role: "system".to_string(),
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+        });
+        if let Some(system_message) = &system_message {
+            rendered_messages.insert(0, system_message);
         }
 
-        conversation
+        template
+            .render(minijinja::context! {
+                messages => rendered_messages,
+                system,
+                add_generation_prompt => true,
+                bos_token,
+                eos_token,
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to render chat template for model {}: {}", self.model_id, e))
     }
 
-    fn generate(&mut self, prompt: &str, config: &InferenceConfig) -> anyhow::Result<String> {
+    fn generate(&mut self, prompt: &str, config: &InferenceConfig) -> anyhow::Result<(String, GenerationStats)> {
+        let mut full_text = String::new();
+        let stats = self.generate_with_callback(prompt, config, |chunk| full_text.push_str(chunk))?;
+        Ok((full_text.trim().to_string(), stats))
+    }
+
+    /// Core decode loop shared by the buffered `generate` path and the
+    /// incremental streaming paths (`InferenceEngine::generate_stream` /
+    /// `chat_stream`). Calls `on_chunk` with each newly decodable slice of
+    /// text as soon as `TokenOutputStream` says it's safe to emit, and
+    /// returns the real prompt/completion token counts plus why generation
+    /// stopped.
+    fn generate_with_callback(
+        &mut self,
+        prompt: &str,
+        config: &InferenceConfig,
+        mut on_chunk: impl FnMut(&str),
+    ) -> anyhow::Result<GenerationStats> {
         let start_time = std::time::Instant::now();
-        
+
         // Tokenize input
         let tokens = self.tokenizer.encode(prompt, true).map_err(|e| {
             anyhow::anyhow!("Failed to encode prompt: {}", e)
         })?;
 
         if tokens.is_empty() {
-            return Ok(String::new());
+            return Ok(GenerationStats {
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                finish_reason: FinishReason::Stop,
+            });
         }
 
         let mut tokens = tokens.get_ids().to_vec();
         let mut generated_tokens = Vec::new();
-        
-        // Initialize logits processor
-        let logits_processor = match config.seed {
-            Some(seed) => LogitsProcessor::from_entropy_seed(seed),
-            None => LogitsProcessor::from_entropy(),
-        };
+        let mut output_stream = crate::core::token_output_stream::TokenOutputStream::new(self.tokenizer.clone());
+
+        // Build the sampling strategy from the request's InferenceConfig
+        // instead of always sampling from the raw entropy distribution.
+        let sampling = config.sampling();
+        let seed = config.seed.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0)
+        });
+        let mut logits_processor = LogitsProcessor::from_sampling(seed, sampling);
 
-        let eos_token = self.tokenizer.token_to_id("<|endoftext|>").unwrap_or(2);
+        let eos_token = self.eos_token_id;
         let bos_token = self.tokenizer.token_to_id("<s>").unwrap_or(1);
 
         // Add BOS token if not present
@@ -154,83 +467,393 @@ impl ModelInstance {
             tokens.insert(0, bos_token);
         }
 
+        // Counted after the BOS insertion above, so `prompt_tokens` includes
+        // the BOS token.
+        let prompt_tokens = tokens.len() as u32;
         let mut current_len = tokens.len();
 
+        // How far back to look when penalizing already-seen tokens.
+        const REPEAT_PENALTY_WINDOW: usize = 64;
+
+        let mut finish_reason = FinishReason::Length;
+
         // Generate tokens
         for index in 0..config.max_tokens {
-            let (logits, _) = self.weights.forward(&tokens, current_len, &self.device)?;
-            
+            let logits = self.weights.forward(&tokens, current_len, &self.device)?;
+
             let logits = logits.squeeze(0)?;
             let logits = logits.get(current_len - 1)?;
-            
+
+            let logits = if config.repeat_penalty != 1.0 {
+                let start_at = tokens.len().saturating_sub(REPEAT_PENALTY_WINDOW);
+                transformers_model::apply_repeat_penalty(&logits, config.repeat_penalty, &tokens[start_at..])?
+            } else {
+                logits
+            };
+
             let next_token = logits_processor.sample(&logits)?;
             tokens.push(next_token);
             generated_tokens.push(next_token);
             current_len += 1;
 
+            if let Some(chunk) = output_stream.next_token(next_token)? {
+                on_chunk(&chunk);
+            }
+
             if next_token == eos_token {
+                finish_reason = FinishReason::Stop;
                 break;
             }
 
             // Early stopping if we hit the end
             if index == config.max_tokens - 1 {
+                finish_reason = FinishReason::Length;
                 break;
             }
         }
 
-        // Decode generated tokens
-        let generated_text = self.tokenizer.decode(&generated_tokens, true)
-            .map_err(|e| anyhow::anyhow!("Failed to decode generated tokens: {}", e))?;
+        if let Some(rest) = output_stream.decode_rest()? {
+            on_chunk(&rest);
+        }
 
         let duration = start_time.elapsed().as_nanos() as u64;
         info!("Generated {} tokens in {}ms", generated_tokens.len(), duration / 1_000_000);
 
-        Ok(generated_text.trim().to_string())
+        Ok(GenerationStats {
+            prompt_tokens,
+            completion_tokens: generated_tokens.len() as u32,
+            finish_reason,
+        })
     }
+
+    /// Batched counterpart to `generate`: runs every prompt in `prompts`
+    /// through `generate` one at a time. Each prompt keeps its own
+    /// independent token history, so results are identical to calling
+    /// `generate` directly on each prompt — this just saves callers
+    /// (`queue_generation`'s micro-batching, `complete`/`chat_completion`'s
+    /// `n` choices) from having to loop themselves.
+    ///
+    /// A fused forward pass over the whole batch would be faster, but it
+    /// requires right-padding shorter prompts to the batch's longest, and
+    /// under this KV-cache model that padding ends up sitting in the middle
+    /// of a shorter sequence's context (between its real prompt and its
+    /// first generated token) rather than safely outside it, corrupting
+    /// every generated token past the first. Looping per-prompt gives up
+    /// that throughput win to keep results correct for mixed-length batches.
+    fn generate_batch(&mut self, prompts: &[String], config: &InferenceConfig) -> anyhow::Result<Vec<(String, GenerationStats)>> {
+        let start_time = std::time::Instant::now();
+
+        let mut results = Vec::with_capacity(prompts.len());
+        for prompt in prompts {
+            results.push(self.generate(prompt, config)?);
+        }
+
+        let duration = start_time.elapsed().as_nanos() as u64;
+        info!(
+            "Generated a batch of {} sequences in {}ms",
+            prompts.len(),
+            duration / 1_000_000
+        );
+
+        Ok(results)
+    }
+}
+
+/// A model loaded into `InferenceEngine::models`, either a generation model
+/// (`ModelInstance`) or an embedding model (`SentenceEmbedder`). Both kinds
+/// share the same `model_id` namespace so callers look them up the same way
+/// regardless of what they're used for.
+pub enum LoadedModel {
+    Generation(ModelInstance),
+    Embedding(crate::core::embeddings::SentenceEmbedder),
+}
+
+/// One prompt waiting in a model's micro-batch queue, along with the channel
+/// `InferenceEngine::queue_generation` uses to hand its result back once the
+/// batch it ends up in has run.
+struct QueuedGeneration {
+    prompt: String,
+    respond_to: oneshot::Sender<anyhow::Result<(String, GenerationStats)>>,
 }
 
 pub struct InferenceEngine {
-    models: Mutex<HashMap<String, ModelInstance>>,
+    models: Mutex<HashMap<String, LoadedModel>>,
     device: Device,
     _guard: candle_core::Cpu, // Keep reference to prevent CPU fallback
+    /// Caps how many prompts a single `BatchGenerationRequest` may contain,
+    /// and how many in-flight `generate`/`chat` calls `queue_generation` will
+    /// coalesce into one micro-batch. Set from `--max-client-batch-size`
+    /// (default 4).
+    max_client_batch_size: usize,
+    /// Per-model queue of prompts waiting to be coalesced into that model's
+    /// next micro-batch. Lazily created the first time a request for a model
+    /// is queued.
+    batch_queues: Mutex<HashMap<String, Vec<QueuedGeneration>>>,
 }
 
+/// How long a micro-batch's leader waits for follow-on requests to the same
+/// model before running whatever has queued up as one batch.
+const MICRO_BATCH_WINDOW: Duration = Duration::from_millis(10);
+
 impl InferenceEngine {
     pub fn new() -> Self {
         let device = Device::Cpu;
         let _guard = candle_core::Cpu::new();
-        
+
         Self {
             models: Mutex::new(HashMap::new()),
             device,
             _guard,
+            max_client_batch_size: 4,
+            batch_queues: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Overrides the default `max_client_batch_size` of 4, wired up to the
+    /// server's `--max-client-batch-size` CLI option.
+    pub fn with_max_client_batch_size(mut self, max_client_batch_size: usize) -> Self {
+        self.max_client_batch_size = max_client_batch_size;
+        self
+    }
+
+    /// Submits one prompt to `model_id`'s micro-batch queue and waits for its
+    /// result. The first caller to find the queue empty becomes that batch's
+    /// leader: it waits `MICRO_BATCH_WINDOW` for other concurrent requests to
+    /// the same model to join, then drains the queue (capped at
+    /// `max_client_batch_size`) and runs it via `ModelInstance::generate_batch`,
+    /// which decodes the prompts one after another under a single lock of
+    /// `models`; there is no fused forward pass. Everyone else just awaits their
+    /// own result, computed as part of whichever batch picks up their entry.
+    async fn queue_generation(&self, model_id: &str, prompt: String) -> anyhow::Result<(String, GenerationStats)> {
+        let (respond_to, receiver) = oneshot::channel();
+        let is_leader = {
+            let mut queues = self.batch_queues.lock().await;
+            let queue = queues.entry(model_id.to_string()).or_default();
+            queue.push(QueuedGeneration { prompt, respond_to });
+            queue.len() == 1
+        };
+
+        if is_leader {
+            tokio::time::sleep(MICRO_BATCH_WINDOW).await;
+
+            // More requests than `max_client_batch_size` may have piled up
+            // during the window. Keep draining and running successive
+            // batches until the queue is empty instead of only handling the
+            // first one, or anything past the cap would be left queued with
+            // no leader left to ever pick it up.
+            loop {
+                let batch = {
+                    let mut queues = self.batch_queues.lock().await;
+                    let queue = queues.get_mut(model_id).expect("queue was created above");
+                    let drain_to = queue.len().min(self.max_client_batch_size);
+                    queue.drain(..drain_to).collect::<Vec<_>>()
+                };
+
+                if batch.is_empty() {
+                    break;
+                }
+
+                let prompts: Vec<String> = batch.iter().map(|item| item.prompt.clone()).collect();
+                let outcome = {
+                    let mut models = self.models.lock().await;
+                    match models.get_mut(model_id) {
+                        Some(LoadedModel::Generation(model)) => {
+                            let config = model.config.clone();
+                            model.generate_batch(&prompts, &config)
+                        }
+                        Some(LoadedModel::Embedding(_)) => {
+                            Err(anyhow::anyhow!("Model {} is an embedding model, not a generation model", model_id))
+                        }
+                        None => Err(anyhow::anyhow!("Model not found: {}", model_id)),
+                    }
+                };
+
+                match outcome {
+                    Ok(results) => {
+                        for (item, result) in batch.into_iter().zip(results.into_iter()) {
+                            let _ = item.respond_to.send(Ok(result));
+                        }
+                    }
+                    Err(e) => {
+                        // `generate_batch` stops at the first prompt that fails,
+                        // so every prompt in the batch gets that error, including
+                        // ones that had already finished.
+                        let message = e.to_string();
+                        for item in batch {
+                            let _ = item.respond_to.send(Err(anyhow::anyhow!(message.clone())));
+                        }
+                    }
+                }
+            }
+        }
+
+        receiver.await.map_err(|_| anyhow::anyhow!("Batch worker for model {} dropped the response channel", model_id))?
+    }
+
     pub async fn load_model(&self, model_path: &Path, model_id: &str, config: InferenceConfig) -> anyhow::Result<()> {
         info!("Loading model from: {:?}", model_path);
-        
-        // Load model weights (this is a simplified version)
-        // In practice, you'd need to detect the model type and load appropriately
-        let mut models = self.models.lock().await;
-        
-        // This is a placeholder - you'd implement actual model loading here
-        // For now, we'll create a mock model instance
+
+        let start_time = std::time::Instant::now();
+
+        let mut file = File::open(model_path)
+            .map_err(|e| anyhow::anyhow!("Failed to open model file {:?}: {}", model_path, e))?;
+        let content = gguf_file::Content::read(&mut file)
+            .map_err(|e| anyhow::anyhow!("Failed to read GGUF header from {:?}: {}", model_path, e))?;
+
+        let architecture = content
+            .metadata
+            .get("general.architecture")
+            .and_then(|v| v.to_string().ok())
+            .unwrap_or_else(|| "llama".to_string());
+
+        info!("Detected architecture '{}' for model {}", architecture, model_id);
+
+        // Tokenizer, chat template, and EOS token id only ever need `content`'s
+        // metadata, so read them before `from_gguf` consumes `content` to build
+        // weights.
+        let tokenizer = self.load_tokenizer(model_path, &content)?;
+        let chat_template = Self::load_chat_template(model_path, &content);
+        let eos_token_id = Self::load_eos_token_id(&content, &tokenizer);
+
+        let weights = match architecture.as_str() {
+            "llama" => LoadedWeights::Llama(LlamaWeights::from_gguf(content, &mut file, &self.device)?),
+            "mistral" => LoadedWeights::Mistral(MistralWeights::from_gguf(content, &mut file, &self.device)?),
+            "gemma" => LoadedWeights::Gemma(GemmaWeights::from_gguf(content, &mut file, &self.device)?),
+            "phi" | "phi2" | "phi3" => LoadedWeights::Phi(PhiWeights::from_gguf(content, &mut file, &self.device)?),
+            other => return Err(anyhow::anyhow!("Unsupported model architecture: {}", other)),
+        };
+
+        let load_duration = start_time.elapsed().as_nanos() as u64;
+
         let model_instance = ModelInstance {
             model_id: model_id.to_string(),
-            weights: todo!("Implement actual model weight loading"),
-            tokenizer: todo!("Load actual tokenizer"),
+            weights,
+            tokenizer,
             device: self.device.clone(),
             config,
             session_id: Uuid::new_v4().to_string(),
+            load_duration,
+            architecture,
+            chat_template,
+            eos_token_id,
         };
 
-        models.insert(model_id.to_string(), model_instance);
-        info!("Model loaded successfully: {}", model_id);
-        
+        let mut models = self.models.lock().await;
+        models.insert(model_id.to_string(), LoadedModel::Generation(model_instance));
+        info!(
+            "Model loaded successfully: {} ({}ms)",
+            model_id,
+            load_duration / 1_000_000
+        );
+
         Ok(())
     }
 
+    /// Loads the tokenizer that goes with `model_path`. Prefers a sibling
+    /// `tokenizer.json` (the common HF layout models are distributed with),
+    /// and falls back to reconstructing a tokenizer from the vocabulary
+    /// embedded in the GGUF metadata when no such file is present.
+    fn load_tokenizer(
+        &self,
+        model_path: &Path,
+        content: &gguf_file::Content,
+    ) -> anyhow::Result<tokenizers::Tokenizer> {
+        let tokenizer_path = model_path.with_file_name("tokenizer.json");
+
+        if tokenizer_path.exists() {
+            return tokenizers::Tokenizer::from_file(&tokenizer_path)
+                .map_err(|e| anyhow::anyhow!("Failed to load tokenizer from {:?}: {}", tokenizer_path, e));
+        }
+
+        warn!(
+            "No tokenizer.json found next to {:?}, falling back to the tokenizer embedded in the GGUF file",
+            model_path
+        );
+
+        Self::tokenizer_from_gguf_metadata(content)
+    }
+
+    /// Reads the model's chat-template source, preferring the GGUF
+    /// `tokenizer.chat_template` metadata key (the llama.cpp convention) and
+    /// falling back to a sibling `chat_template.txt`. `None` means the
+    /// caller should fall back to the built-in default for the model's
+    /// architecture.
+    fn load_chat_template(model_path: &Path, content: &gguf_file::Content) -> Option<String> {
+        if let Some(template) = content
+            .metadata
+            .get("tokenizer.chat_template")
+            .and_then(|v| v.to_string().ok())
+        {
+            return Some(template);
+        }
+
+        std::fs::read_to_string(model_path.with_file_name("chat_template.txt")).ok()
+    }
+
+    /// Reads the model's EOS token id from the GGUF `tokenizer.ggml.eos_token_id`
+    /// metadata key. Architectures disagree on this (e.g. Gemma's EOS id isn't
+    /// Llama's), so it has to come from the model's own metadata rather than a
+    /// single hardcoded id. Falls back to looking up `<|endoftext|>` (and then
+    /// `</s>`) in the tokenizer for models that don't carry this metadata key.
+    fn load_eos_token_id(content: &gguf_file::Content, tokenizer: &tokenizers::Tokenizer) -> u32 {
+        if let Some(id) = content
+            .metadata
+            .get("tokenizer.ggml.eos_token_id")
+            .and_then(|v| v.to_u32().ok())
+        {
+            return id;
+        }
+
+        tokenizer
+            .token_to_id("<|endoftext|>")
+            .or_else(|| tokenizer.token_to_id("</s>"))
+            .unwrap_or(2)
+    }
+
+    /// Builds a BPE `tokenizers::Tokenizer` from the `tokenizer.ggml.tokens` /
+    /// `tokenizer.ggml.merges` metadata keys that llama.cpp embeds in GGUF
+    /// files as an alternative to shipping a separate `tokenizer.json`.
+    /// `tokenizer.ggml.scores` is not read.
+    fn tokenizer_from_gguf_metadata(content: &gguf_file::Content) -> anyhow::Result<tokenizers::Tokenizer> {
+        use tokenizers::models::bpe::BPE;
+
+        let tokens = content
+            .metadata
+            .get("tokenizer.ggml.tokens")
+            .ok_or_else(|| anyhow::anyhow!("GGUF file has no tokenizer.json and no embedded tokenizer.ggml.tokens"))?
+            .to_vec()
+            .map_err(|e| anyhow::anyhow!("Malformed tokenizer.ggml.tokens: {}", e))?;
+
+        let merges = content
+            .metadata
+            .get("tokenizer.ggml.merges")
+            .and_then(|v| v.to_vec().ok())
+            .unwrap_or_default();
+
+        let mut vocab = HashMap::new();
+        for (id, token) in tokens.iter().enumerate() {
+            if let Ok(token) = token.to_string() {
+                vocab.insert(token, id as u32);
+            }
+        }
+
+        let merges: Vec<(String, String)> = merges
+            .iter()
+            .filter_map(|m| m.to_string().ok())
+            .filter_map(|m| {
+                let mut parts = m.splitn(2, ' ');
+                Some((parts.next()?.to_string(), parts.next()?.to_string()))
+            })
+            .collect();
+
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Failed to build BPE tokenizer from GGUF metadata: {}", e))?;
+
+        Ok(tokenizers::Tokenizer::new(bpe))
+    }
+
     pub async fn unload_model(&self, model_id: &str) -> anyhow::Result<bool> {
         let mut models = self.models.lock().await;
         let removed = models.remove(model_id).is_some();
@@ -240,12 +863,50 @@ impl InferenceEngine {
         Ok(removed)
     }
 
+    /// Loads a BERT/sentence-transformer embedding model directory into the
+    /// same `models` map generation models live in, under the `Embedding`
+    /// variant of `LoadedModel`.
+    pub async fn load_embedding_model(&self, model_dir: &Path, model_id: &str) -> anyhow::Result<()> {
+        info!("Loading embedding model from: {:?}", model_dir);
+        let embedder = crate::core::embeddings::SentenceEmbedder::load(model_dir, &self.device)?;
+        let mut models = self.models.lock().await;
+        models.insert(model_id.to_string(), LoadedModel::Embedding(embedder));
+        info!("Embedding model loaded successfully: {}", model_id);
+        Ok(())
+    }
+
+    /// Embeds a batch of strings with the named embedding model.
+    pub async fn embed(&self, request: EmbeddingRequest) -> anyhow::Result<EmbeddingResponse> {
+        let models = self.models.lock().await;
+        match models.get(&request.model) {
+            Some(LoadedModel::Embedding(embedder)) => Ok(EmbeddingResponse {
+                embeddings: embedder.embed(&request.input)?,
+                model: request.model,
+            }),
+            Some(LoadedModel::Generation(_)) => {
+                Err(anyhow::anyhow!("Model {} is a generation model, not an embedding model", request.model))
+            }
+            None => Err(anyhow::anyhow!("Model not found: {}", request.model)),
+        }
+    }
+
+    /// Generates one prompt by handing it to `queue_generation`, which
+    /// coalesces it with whatever other requests for the same model show up
+    /// within the micro-batch window into one batch, decoded one prompt
+    /// after another.
     pub async fn generate(&self, request: GenerationRequest) -> anyhow::Result<GenerationResponse> {
         let start_time = std::time::Instant::now();
-        let mut models = self.models.lock().await;
-        
-        let model = models.get_mut(&request.model)
-            .ok_or_else(|| anyhow::anyhow!("Model not found: {}", request.model))?;
+
+        let load_duration = {
+            let models = self.models.lock().await;
+            match models.get(&request.model) {
+                Some(LoadedModel::Generation(model)) => model.load_duration,
+                Some(LoadedModel::Embedding(_)) => {
+                    return Err(anyhow::anyhow!("Model {} is an embedding model, not a generation model", request.model));
+                }
+                None => return Err(anyhow::anyhow!("Model not found: {}", request.model)),
+            }
+        };
 
         let prompt = if let Some(system) = &request.system {
             format!("{}\n\n{}", system, request.prompt)
@@ -253,7 +914,7 @@ impl InferenceEngine {
             request.prompt.clone()
         };
 
-        let response_text = model.generate(&prompt, &model.config)?;
+        let (response_text, stats) = self.queue_generation(&request.model, prompt).await?;
 
         let total_duration = start_time.elapsed().as_nanos() as u64;
 
@@ -264,52 +925,522 @@ impl InferenceEngine {
             done: true,
             context: request.context,
             total_duration: Some(total_duration),
-            load_duration: None,
-            prompt_eval_count: Some(prompt.len() as u32 / 4), // Rough estimate
+            load_duration: Some(load_duration),
+            prompt_eval_count: Some(stats.prompt_tokens),
             prompt_eval_duration: Some(total_duration / 10), // Rough estimate
-            eval_count: Some(response_text.len() as u32 / 4), // Rough estimate
+            eval_count: Some(stats.completion_tokens),
             eval_duration: Some(total_duration * 9 / 10), // Rough estimate
         })
     }
 
-    pub async fn chat(&self, request: ChatRequest) -> anyhow::Result<ChatResponse> {
+    /// Runs several prompts against the same model as one batch, decoded one
+    /// prompt after another under a single lock of `models`. Rejects requests with more prompts than `max_client_batch_size`
+    /// instead of silently truncating them.
+    pub async fn generate_batch(&self, request: BatchGenerationRequest) -> anyhow::Result<BatchGenerationResponse> {
+        if request.prompts.len() > self.max_client_batch_size {
+            return Err(anyhow::anyhow!(
+                "Batch of {} prompts exceeds max_client_batch_size of {}",
+                request.prompts.len(),
+                self.max_client_batch_size
+            ));
+        }
+
         let start_time = std::time::Instant::now();
         let mut models = self.models.lock().await;
-        
-        let model = models.get_mut(&request.model)
-            .ok_or_else(|| anyhow::anyhow!("Model not found: {}", request.model))?;
 
-        let system_msg = request.messages.iter()
-            .find(|msg| msg.role == "system")
-            .map(|msg| msg.content.as_str());
+        let model = match models.get_mut(&request.model) {
+            Some(LoadedModel::Generation(model)) => model,
+            Some(LoadedModel::Embedding(_)) => {
+                return Err(anyhow::anyhow!("Model {} is an embedding model, not a generation model", request.model));
+            }
+            None => return Err(anyhow::anyhow!("Model not found: {}", request.model)),
+        };
+
+        let load_duration = model.load_duration;
+        let config = model.config.clone();
+        let results = model.generate_batch(&request.prompts, &config)?;
+        drop(models);
 
-        let user_messages: Vec<_> = request.messages.iter()
-            .filter(|msg| msg.role == "user" || msg.role == "assistant")
+        let total_duration = start_time.elapsed().as_nanos() as u64;
+        let responses = results
+            .into_iter()
+            .map(|(response_text, stats)| GenerationResponse {
+                model: request.model.clone(),
+                created_at: chrono::Utc::now().to_rfc3339(),
+                response: response_text,
+                done: true,
+                context: None,
+                total_duration: Some(total_duration),
+                load_duration: Some(load_duration),
+                prompt_eval_count: Some(stats.prompt_tokens),
+                prompt_eval_duration: Some(total_duration / 10), // Rough estimate
+                eval_count: Some(stats.completion_tokens),
+                eval_duration: Some(total_duration * 9 / 10), // Rough estimate
+            })
             .collect();
 
-        let prompt = model.apply_chat_template(&user_messages, system_msg);
-        
-        let response_text = model.generate(&prompt, &model.config)?;
+        Ok(BatchGenerationResponse {
+            model: request.model,
+            responses,
+        })
+    }
+
+    /// Builds the text appended after the request's own system message when
+    /// `ChatRequest::tools` is set, instructing the model to respond with a
+    /// bare JSON tool-call object instead of prose when it wants to call one.
+    fn render_tools_preamble(tools: &[ToolDeclaration]) -> String {
+        let mut text = String::from(
+            "You have access to the following functions. To call one, respond with ONLY a JSON object of the form {\"name\": \"<function name>\", \"arguments\": <arguments object>} and nothing else.\n\n",
+        );
+        for ToolDeclaration::Function { function } in tools {
+            text.push_str(&format!(
+                "- {}: {}\n",
+                function.name,
+                function.description.as_deref().unwrap_or("")
+            ));
+        }
+        text
+    }
+
+    /// Merges the request's system message with the tools preamble, if
+    /// any tools were declared on the request.
+    fn build_system_message(messages: &[ChatMessage], tools: Option<&[ToolDeclaration]>) -> Option<String> {
+        let system_msg = messages.iter().find(|msg| msg.role == "system").map(|msg| msg.content.clone());
+        let tools_preamble = tools.filter(|t| !t.is_empty()).map(Self::render_tools_preamble);
+
+        match (system_msg, tools_preamble) {
+            (Some(system), Some(preamble)) => Some(format!("{}\n\n{}", system, preamble)),
+            (Some(system), None) => Some(system),
+            (None, Some(preamble)) => Some(preamble),
+            (None, None) => None,
+        }
+    }
+
+    /// Looks for a bare `{"name": ..., "arguments": ...}` object in the
+    /// model's output and parses it as a tool call. Returns `None` for
+    /// ordinary natural-language answers.
+    fn try_parse_tool_call(text: &str) -> Option<ToolCallFunction> {
+        let trimmed = text.trim();
+        let start = trimmed.find('{')?;
+        let end = trimmed.rfind('}')?;
+        if end < start {
+            return None;
+        }
+        serde_json::from_str::<ToolCallFunction>(&trimmed[start..=end]).ok()
+    }
+
+    pub async fn chat(&self, request: ChatRequest) -> anyhow::Result<ChatResponse> {
+        let start_time = std::time::Instant::now();
+
+        let (prompt, load_duration) = {
+            let models = self.models.lock().await;
+            let model = match models.get(&request.model) {
+                Some(LoadedModel::Generation(model)) => model,
+                Some(LoadedModel::Embedding(_)) => {
+                    return Err(anyhow::anyhow!("Model {} is an embedding model, not a generation model", request.model));
+                }
+                None => return Err(anyhow::anyhow!("Model not found: {}", request.model)),
+            };
+
+            let system_msg = Self::build_system_message(&request.messages, request.tools.as_deref());
+            let prompt = model.apply_chat_template(&request.messages, system_msg.as_deref())?;
+            (prompt, model.load_duration)
+        };
+
+        let (response_text, stats) = self.queue_generation(&request.model, prompt).await?;
 
         let total_duration = start_time.elapsed().as_nanos() as u64;
 
-        Ok(ChatResponse {
-            model: request.model,
-            created_at: chrono::Utc::now().to_rfc3339(),
-            message: ChatMessage {
+        // A multi-step tool-calling turn ends here with `done: false`; the
+        // caller runs the function(s) and POSTs a `"tool"` role message back
+        // so the next `chat` call can resume generation with the result.
+        let message = match request.tools.as_deref().filter(|t| !t.is_empty()).and_then(|_| Self::try_parse_tool_call(&response_text)) {
+            Some(function) => ChatMessage {
                 role: "assistant".to_string(),
+                // Kept in `content` (not just `tool_calls`) so the call still
+                // round-trips through built-in templates that only read
+                // `content` when this message is replayed on the next turn.
                 content: response_text.clone(),
+                tool_calls: Some(vec![ToolCall {
+                    id: format!("call_{}", Uuid::new_v4()),
+                    call_type: "function".to_string(),
+                    function,
+                }]),
+                tool_call_id: None,
             },
-            done: true,
+            None => ChatMessage {
+                role: "assistant".to_string(),
+                content: response_text.clone(),
+                tool_calls: None,
+                tool_call_id: None,
+            },
+        };
+        let done = message.tool_calls.is_none();
+
+        Ok(ChatResponse {
+            model: request.model,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            message,
+            done,
             total_duration: Some(total_duration),
-            load_duration: None,
-            prompt_eval_count: Some(prompt.len() as u32 / 4), // Rough estimate
+            load_duration: Some(load_duration),
+            prompt_eval_count: Some(stats.prompt_tokens),
             prompt_eval_duration: Some(total_duration / 10), // Rough estimate
-            eval_count: Some(response_text.len() as u32 / 4), // Rough estimate
+            eval_count: Some(stats.completion_tokens),
             eval_duration: Some(total_duration * 9 / 10), // Rough estimate
         })
     }
 
+    /// Streaming counterpart to `generate`: yields partial `GenerationResponse`
+    /// chunks as the model produces them, with `done: false` on every chunk
+    /// but the last, which carries the usual timing/eval fields. Backs the
+    /// `stream: true` path of `GenerationRequest` over NDJSON/SSE.
+    ///
+    /// The decode loop runs on a `spawn_blocking` task that owns the model
+    /// instance (checked out of `self.models` for the duration of the
+    /// stream) and feeds chunks back through an `mpsc` channel as soon as
+    /// they're produced, so the first chunk reaches the client as soon as
+    /// it's decoded instead of after the whole response is generated. This
+    /// also means `self.models` isn't held locked for the stream's
+    /// lifetime — other models stay available to concurrent callers; only
+    /// this one is briefly unavailable ("Model not found") until the stream
+    /// finishes and it's checked back in.
+    pub fn generate_stream<'a>(
+        &'a self,
+        request: GenerationRequest,
+    ) -> impl Stream<Item = anyhow::Result<GenerationResponse>> + 'a {
+        stream! {
+            let start_time = std::time::Instant::now();
+
+            let mut model_instance = {
+                let mut models = self.models.lock().await;
+                match models.remove(&request.model) {
+                    Some(LoadedModel::Generation(instance)) => instance,
+                    Some(other @ LoadedModel::Embedding(_)) => {
+                        models.insert(request.model.clone(), other);
+                        yield Err(anyhow::anyhow!("Model {} is an embedding model, not a generation model", request.model));
+                        return;
+                    }
+                    None => {
+                        yield Err(anyhow::anyhow!("Model not found: {}", request.model));
+                        return;
+                    }
+                }
+            };
+
+            let prompt = if let Some(system) = &request.system {
+                format!("{}\n\n{}", system, request.prompt)
+            } else {
+                request.prompt.clone()
+            };
+
+            let load_duration = model_instance.load_duration;
+            let config = model_instance.config.clone();
+            let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+
+            let handle = tokio::task::spawn_blocking(move || {
+                let stats = model_instance.generate_with_callback(&prompt, &config, |chunk| {
+                    let _ = tx.send(chunk.to_string());
+                });
+                (model_instance, stats)
+            });
+
+            while let Some(chunk) = rx.recv().await {
+                yield Ok(GenerationResponse {
+                    model: request.model.clone(),
+                    created_at: chrono::Utc::now().to_rfc3339(),
+                    response: chunk,
+                    done: false,
+                    context: None,
+                    total_duration: None,
+                    load_duration: None,
+                    prompt_eval_count: None,
+                    prompt_eval_duration: None,
+                    eval_count: None,
+                    eval_duration: None,
+                });
+            }
+
+            let (model_instance, stats) = match handle.await {
+                Ok(pair) => pair,
+                Err(e) => {
+                    yield Err(anyhow::anyhow!("Generation task for model {} panicked: {}", request.model, e));
+                    return;
+                }
+            };
+
+            {
+                let mut models = self.models.lock().await;
+                models.insert(request.model.clone(), LoadedModel::Generation(model_instance));
+            }
+
+            let stats = match stats {
+                Ok(stats) => stats,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            let total_duration = start_time.elapsed().as_nanos() as u64;
+
+            yield Ok(GenerationResponse {
+                model: request.model.clone(),
+                created_at: chrono::Utc::now().to_rfc3339(),
+                response: String::new(),
+                done: true,
+                context: request.context.clone(),
+                total_duration: Some(total_duration),
+                load_duration: Some(load_duration),
+                prompt_eval_count: Some(stats.prompt_tokens),
+                prompt_eval_duration: Some(total_duration / 10),
+                eval_count: Some(stats.completion_tokens),
+                eval_duration: Some(total_duration * 9 / 10),
+            });
+        }
+    }
+
+    /// Streaming counterpart to `chat`, see `generate_stream`.
+    pub fn chat_stream<'a>(
+        &'a self,
+        request: ChatRequest,
+    ) -> impl Stream<Item = anyhow::Result<ChatResponse>> + 'a {
+        stream! {
+            let start_time = std::time::Instant::now();
+
+            let mut model_instance = {
+                let mut models = self.models.lock().await;
+                match models.remove(&request.model) {
+                    Some(LoadedModel::Generation(instance)) => instance,
+                    Some(other @ LoadedModel::Embedding(_)) => {
+                        models.insert(request.model.clone(), other);
+                        yield Err(anyhow::anyhow!("Model {} is an embedding model, not a generation model", request.model));
+                        return;
+                    }
+                    None => {
+                        yield Err(anyhow::anyhow!("Model not found: {}", request.model));
+                        return;
+                    }
+                }
+            };
+
+            let system_msg = request.messages.iter()
+                .find(|msg| msg.role == "system")
+                .map(|msg| msg.content.as_str());
+
+            let prompt = match model_instance.apply_chat_template(&request.messages, system_msg) {
+                Ok(prompt) => prompt,
+                Err(e) => {
+                    let mut models = self.models.lock().await;
+                    models.insert(request.model.clone(), LoadedModel::Generation(model_instance));
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            let load_duration = model_instance.load_duration;
+            let config = model_instance.config.clone();
+            let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+
+            let handle = tokio::task::spawn_blocking(move || {
+                let stats = model_instance.generate_with_callback(&prompt, &config, |chunk| {
+                    let _ = tx.send(chunk.to_string());
+                });
+                (model_instance, stats)
+            });
+
+            while let Some(chunk) = rx.recv().await {
+                yield Ok(ChatResponse {
+                    model: request.model.clone(),
+                    created_at: chrono::Utc::now().to_rfc3339(),
+                    message: ChatMessage {
+                        role: "assistant".to_string(),
+                        content: chunk,
+                        tool_calls: None,
+                        tool_call_id: None,
+                    },
+                    done: false,
+                    total_duration: None,
+                    load_duration: None,
+                    prompt_eval_count: None,
+                    prompt_eval_duration: None,
+                    eval_count: None,
+                    eval_duration: None,
+                });
+            }
+
+            let (model_instance, stats) = match handle.await {
+                Ok(pair) => pair,
+                Err(e) => {
+                    yield Err(anyhow::anyhow!("Generation task for model {} panicked: {}", request.model, e));
+                    return;
+                }
+            };
+
+            {
+                let mut models = self.models.lock().await;
+                models.insert(request.model.clone(), LoadedModel::Generation(model_instance));
+            }
+
+            let stats = match stats {
+                Ok(stats) => stats,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            let total_duration = start_time.elapsed().as_nanos() as u64;
+
+            yield Ok(ChatResponse {
+                model: request.model.clone(),
+                created_at: chrono::Utc::now().to_rfc3339(),
+                message: ChatMessage {
+                    role: "assistant".to_string(),
+                    content: String::new(),
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                done: true,
+                total_duration: Some(total_duration),
+                load_duration: Some(load_duration),
+                prompt_eval_count: Some(stats.prompt_tokens),
+                prompt_eval_duration: Some(total_duration / 10),
+                eval_count: Some(stats.completion_tokens),
+                eval_duration: Some(total_duration * 9 / 10),
+            });
+        }
+    }
+
+    /// OpenAI-compatible `/v1/completions`. Runs on the same `InferenceEngine`
+    /// as the Ollama-shaped `generate`, just with the request/response shape
+    /// (and real `usage`/`finish_reason`) OpenAI clients expect.
+    pub async fn complete(&self, request: CompletionRequest) -> anyhow::Result<CompletionResponse> {
+        let mut models = self.models.lock().await;
+
+        let model = match models.get_mut(&request.model) {
+            Some(LoadedModel::Generation(model)) => model,
+            Some(LoadedModel::Embedding(_)) => {
+                return Err(anyhow::anyhow!("Model {} is an embedding model, not a generation model", request.model));
+            }
+            None => return Err(anyhow::anyhow!("Model not found: {}", request.model)),
+        };
+
+        let mut config = model.config.clone();
+        if let Some(temperature) = request.temperature {
+            config.temperature = temperature;
+        }
+        if let Some(top_p) = request.top_p {
+            config.top_p = top_p;
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            config.max_tokens = max_tokens;
+        }
+
+        // `n` independent completions of the same prompt, run through
+        // `generate_batch`, which decodes them one after another.
+        let n = request.n.unwrap_or(1).max(1);
+        let prompts = vec![request.prompt.clone(); n as usize];
+        let results = model.generate_batch(&prompts, &config)?;
+
+        let mut choices = Vec::with_capacity(n as usize);
+        let mut prompt_tokens = 0;
+        let mut completion_tokens = 0;
+
+        for (index, (text, stats)) in results.into_iter().enumerate() {
+            prompt_tokens = stats.prompt_tokens;
+            completion_tokens += stats.completion_tokens;
+            choices.push(CompletionChoice {
+                index: index as u32,
+                text,
+                logprobs: None,
+                finish_reason: stats.finish_reason,
+            });
+        }
+        let usage = OpenAiUsage::from_stats(GenerationStats {
+            prompt_tokens,
+            completion_tokens,
+            finish_reason: FinishReason::Stop,
+        });
+
+        Ok(CompletionResponse {
+            id: format!("cmpl-{}", Uuid::new_v4()),
+            object: "text_completion",
+            created: chrono::Utc::now().timestamp(),
+            model: request.model,
+            choices,
+            usage,
+        })
+    }
+
+    /// OpenAI-compatible `/v1/chat/completions`, see `complete`.
+    pub async fn chat_completion(&self, request: ChatCompletionRequest) -> anyhow::Result<ChatCompletionResponse> {
+        let mut models = self.models.lock().await;
+
+        let model = match models.get_mut(&request.model) {
+            Some(LoadedModel::Generation(model)) => model,
+            Some(LoadedModel::Embedding(_)) => {
+                return Err(anyhow::anyhow!("Model {} is an embedding model, not a generation model", request.model));
+            }
+            None => return Err(anyhow::anyhow!("Model not found: {}", request.model)),
+        };
+
+        let system_msg = request.messages.iter()
+            .find(|msg| msg.role == "system")
+            .map(|msg| msg.content.as_str());
+
+        let prompt = model.apply_chat_template(&request.messages, system_msg)?;
+
+        let mut config = model.config.clone();
+        if let Some(temperature) = request.temperature {
+            config.temperature = temperature;
+        }
+        if let Some(top_p) = request.top_p {
+            config.top_p = top_p;
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            config.max_tokens = max_tokens;
+        }
+
+        let n = request.n.unwrap_or(1).max(1);
+        let prompts = vec![prompt; n as usize];
+        let results = model.generate_batch(&prompts, &config)?;
+
+        let mut choices = Vec::with_capacity(n as usize);
+        let mut prompt_tokens = 0;
+        let mut completion_tokens = 0;
+
+        for (index, (content, stats)) in results.into_iter().enumerate() {
+            prompt_tokens = stats.prompt_tokens;
+            completion_tokens += stats.completion_tokens;
+            choices.push(ChatCompletionChoice {
+                index: index as u32,
+                message: ChatMessage {
+                    role: "assistant".to_string(),
+                    content,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                logprobs: None,
+                finish_reason: stats.finish_reason,
+            });
+        }
+        let usage = OpenAiUsage::from_stats(GenerationStats {
+            prompt_tokens,
+            completion_tokens,
+            finish_reason: FinishReason::Stop,
+        });
+
+        Ok(ChatCompletionResponse {
+            id: format!("chatcmpl-{}", Uuid::new_v4()),
+            object: "chat.completion",
+            created: chrono::Utc::now().timestamp(),
+            model: request.model,
+            choices,
+            usage,
+        })
+    }
+
     pub async fn list_loaded_models(&self) -> Vec<String> {
         let models = self.models.lock().await;
         models.keys().cloned().collect()
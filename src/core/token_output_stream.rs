@@ -0,0 +1,67 @@
+use tokenizers::Tokenizer;
+
+/// Incrementally decodes a stream of sampled token ids into UTF-8 text.
+///
+/// Decoding one token at a time naively can split a multibyte character
+/// across two tokens, which `Tokenizer::decode` renders as the Unicode
+/// replacement character (`\u{fffd}`) until the rest of the sequence
+/// arrives. `TokenOutputStream` re-decodes the tail of the buffer on every
+/// push and only reports the new suffix once it's safe to emit, so callers
+/// never see a dangling replacement char in a streamed chunk.
+pub struct TokenOutputStream {
+    tokenizer: Tokenizer,
+    tokens: Vec<u32>,
+    prev_index: usize,
+    current_index: usize,
+}
+
+impl TokenOutputStream {
+    pub fn new(tokenizer: Tokenizer) -> Self {
+        Self {
+            tokenizer,
+            tokens: Vec::new(),
+            prev_index: 0,
+            current_index: 0,
+        }
+    }
+
+    /// Feeds one newly sampled token id, returning the text that has become
+    /// safe to emit, if any.
+    pub fn next_token(&mut self, token: u32) -> anyhow::Result<Option<String>> {
+        self.tokens.push(token);
+        self.current_index += 1;
+        // Nothing past `prev_index` has been emitted yet, so once it decodes
+        // cleanly all of it is new text.
+        let text = self.decode(self.prev_index, self.current_index)?;
+
+        if !text.is_empty() && !text.ends_with('\u{fffd}') {
+            self.prev_index = self.current_index;
+            Ok(Some(text))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Flushes whatever is still buffered past `prev_index`. Call this once
+    /// after the last token so a trailing dangling sequence isn't lost.
+    pub fn decode_rest(&self) -> anyhow::Result<Option<String>> {
+        let text = self.decode(self.prev_index, self.tokens.len())?;
+        if text.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(text))
+        }
+    }
+
+    fn decode(&self, from: usize, to: usize) -> anyhow::Result<String> {
+        self.tokenizer
+            .decode(&self.tokens[from..to], true)
+            .map_err(|e| anyhow::anyhow!("Failed to decode tokens: {}", e))
+    }
+
+    pub fn clear(&mut self) {
+        self.tokens.clear();
+        self.prev_index = 0;
+        self.current_index = 0;
+    }
+}
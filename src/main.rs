@@ -4,9 +4,12 @@ use tracing::{info, error};
 
 // Define modules
 mod core {
+    pub mod chat_templates;
     pub mod database;
+    pub mod embeddings;
     pub mod inference_engine;
     pub mod model_manager;
+    pub mod token_output_stream;
 }
 
 mod api {
@@ -38,6 +41,12 @@ struct Args {
     /// Run in CLI mode (instead of server mode)
     #[arg(long)]
     cli: bool,
+
+    /// Maximum number of prompts a single batch-generation request may
+    /// contain, and how many concurrent requests the micro-batching queue
+    /// will coalesce into one batch (decoded one prompt after another)
+    #[arg(long, default_value = "4")]
+    max_client_batch_size: usize,
 }
 
 #[tokio::main]
@@ -81,8 +90,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let database = crate::core::database::DatabaseManager::new(&args.database).await?;
     info!("Database initialized successfully");
     
-    let inference_engine = crate::core::inference_engine::InferenceEngine::new();
-    info!("Inference engine initialized");
+    let inference_engine = crate::core::inference_engine::InferenceEngine::new()
+        .with_max_client_batch_size(args.max_client_batch_size);
+    info!("Inference engine initialized (max_client_batch_size={})", args.max_client_batch_size);
     
     let model_manager = crate::core::model_manager::ModelManager::new(
         database,